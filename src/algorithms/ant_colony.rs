@@ -1,5 +1,6 @@
 use dashmap::DashMap;
 use rand::prelude::*;
+use std::collections::HashMap;
 
 const Q: f64 = 3.0; // Heavily reward length (Length^3) to beat frequency
 const INITIAL_PHEROMONE: f64 = 1.0; 
@@ -92,37 +93,69 @@ impl<'a> AntColony<'a> {
         (tokens, steps)
     }
 
-    /// Genetic Algorithm Selection:
-    /// Keep Top 20% by pruning the bottom 80%.
-    pub fn natural_selection(&self) {
+    // How much the corpus's encoded size is allowed to barely-change by
+    // before we call a token's removal a wash rather than a real loss.
+    const MDL_SLACK_BYTES: usize = 0;
+
+    /// MDL Selection: keep a token only if it pays for its own vocabulary
+    /// overhead.
+    ///
+    /// The old cut pruned purely by pheromone percentile (top 50%), which
+    /// has no idea whether a surviving token actually helps compress the
+    /// corpus. A first pass at this re-tokenized the corpus (or at least
+    /// every affected line) per candidate token to measure its exact
+    /// marginal contribution to encoded size -- that's a real DP pass per
+    /// token, and doesn't finish on a real (text8-sized) corpus no matter
+    /// how cheaply the affected subset is found.
+    ///
+    /// Instead we estimate the same marginal contribution from data the
+    /// ants already produced this generation: `paths` is each line's chosen
+    /// segmentation from `traverse`, handed to us the same way `deposit`
+    /// sees it. Counting how many times a token was actually used is an
+    /// O(corpus) pass with no per-token rescan. Every occurrence of a
+    /// `len`-char token collapses `len` single-char fallback tokens (2
+    /// bytes each, i.e. a token stream of `u16` IDs) into one, at a one-time vocabulary storage cost of `token.len() + 4`
+    /// bytes -- so a token earns its keep once its occurrences have paid
+    /// back that overhead. A token nobody chose this generation isn't
+    /// pulling its weight and is pruned immediately.
+    pub fn natural_selection(&self, paths: &[&[&'a str]]) {
         if self.pheromones.is_empty() { return; }
 
         let active_tokens = self.pheromones.len();
-        
-        // 1. Estimate Pruning Threshold via Sampling
-        // Sorting 6 million items is slow. We can just purge anything below a moving average
-        // or just strict top-k. Let's stick to the user's sort for now but optimize logic.
-        
-        let mut scores: Vec<f64> = self.pheromones.iter().map(|r| *r.value()).collect();
-        // Sort descending
-        scores.sort_unstable_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
-        
-        let keep_ratio = 0.50; // Relaxed selection (50%) to give long tokens time to survive
-        let cut_index = ((active_tokens as f64 * keep_ratio) as usize).max(1);
-        let threshold = scores[cut_index.min(active_tokens - 1)];
-
-        println!("  Natural Selection: Keeping top {:.0}% (Threshold: {:.4}). Active Tokens: {}", 
-            keep_ratio * 100.0, threshold, active_tokens);
-
-        // 2. Prune Weak Links & Evaporate Survivors
-        // Trim 20% of every score (keeping them hungry)
-        self.pheromones.retain(|_, v| {
-            if *v < threshold {
-                return false; // Eliminate
+
+        let mut occurrences: HashMap<&str, usize> = HashMap::new();
+        for path in paths {
+            for &token in *path {
+                if self.pheromones.contains_key(token) {
+                    *occurrences.entry(token).or_insert(0) += 1;
+                }
             }
-            *v *= 0.8;
-            true
-        });
+        }
+
+        let mut to_prune = Vec::new();
+        for entry in self.pheromones.iter() {
+            let token: &str = entry.key();
+            let count = occurrences.get(token).copied().unwrap_or(0);
+
+            if count == 0 {
+                to_prune.push(token.to_string());
+                continue;
+            }
+
+            let bytes_saved = count * token.chars().count().saturating_sub(1) * 2;
+            let vocab_overhead = token.len() + 4;
+
+            if bytes_saved <= vocab_overhead + Self::MDL_SLACK_BYTES {
+                to_prune.push(token.to_string());
+            }
+        }
+
+        println!("  MDL Selection: Pruning {} / {} tokens that don't pay for their vocabulary overhead.",
+            to_prune.len(), active_tokens);
+
+        for token in &to_prune {
+            self.pheromones.remove(token.as_str());
+        }
     }
 
     /// Deposit pheromones using Logistic Growth (Soft Cap)
@@ -151,3 +184,45 @@ impl<'a> AntColony<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_selection_prunes_a_token_no_path_used_this_generation() {
+        let colony = AntColony::new();
+        colony.pheromones.insert("ghost", INITIAL_PHEROMONE);
+        let path: Vec<&str> = vec!["the", " ", "quick"];
+        colony.natural_selection(&[&path]);
+
+        assert!(colony.pheromones.get("ghost").is_none());
+    }
+
+    #[test]
+    fn natural_selection_keeps_a_token_that_pays_for_its_vocabulary_overhead() {
+        let colony = AntColony::new();
+        colony.pheromones.insert("abcdefghij", INITIAL_PHEROMONE);
+
+        // Ten occurrences of the token collapse ten single-char fallback
+        // tokens into one each time; losing it costs far more in
+        // token-stream size than the one-time vocab entry is worth.
+        let path: Vec<&str> = vec!["abcdefghij"; 10];
+        colony.natural_selection(&[&path]);
+
+        assert!(colony.pheromones.get("abcdefghij").is_some());
+    }
+
+    #[test]
+    fn natural_selection_prunes_a_rarely_used_token_that_hasnt_paid_back_its_overhead() {
+        let colony = AntColony::new();
+        colony.pheromones.insert("ab", INITIAL_PHEROMONE);
+
+        // A single use of a 2-char token only saves 2 bytes (one avoided
+        // fallback token) against 2 + 4 = 6 bytes of vocab overhead.
+        let path: Vec<&str> = vec!["ab"];
+        colony.natural_selection(&[&path]);
+
+        assert!(colony.pheromones.get("ab").is_none());
+    }
+}
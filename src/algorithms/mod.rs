@@ -0,0 +1 @@
+pub mod ant_colony;
@@ -1,110 +1,524 @@
-use std::collections::HashMap;
+use aho_corasick::AhoCorasick;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufWriter, Write};
 use std::env;
 
+/// On-disk binary vocab format: a header plus one entry per token, each
+/// carrying its raw bytes, its assigned ID, and its score. Unlike the
+/// tab-separated text format this round-trips any byte sequence -- no
+/// escaping needed -- and skips per-line string parsing entirely.
+#[derive(Serialize, Deserialize)]
+struct VocabFile {
+    max_token_len: usize,
+    entries: Vec<VocabEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VocabEntry {
+    id: u32,
+    bytes: Vec<u8>,
+    score: f64,
+}
+
+/// One live segmentation hypothesis during beam search: the tokens chosen so
+/// far, how far into the text we've consumed, and the cumulative log-prob.
+#[derive(Clone, Debug)]
+struct Hypothesis {
+    tokens: Vec<String>,
+    cursor: usize, // char position reached so far
+    log_prob: f64,
+}
+
+impl PartialEq for Hypothesis {
+    fn eq(&self, other: &Self) -> bool {
+        self.log_prob == other.log_prob
+    }
+}
+impl Eq for Hypothesis {}
+
+impl PartialOrd for Hypothesis {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Hypothesis {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.log_prob.partial_cmp(&other.log_prob).unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
 struct Tokenizer {
     vocab: HashMap<String, f64>,
     max_token_len: usize,
+    // Optional Aho-Corasick automaton over `vocab`'s keys. When present, a
+    // single pass over the input text enumerates every vocab token
+    // starting at every position, instead of probing the HashMap once per
+    // candidate length at every cursor. `pattern_tokens[pattern_id]` maps an
+    // automaton match back to its token string.
+    matcher: Option<AhoCorasick>,
+    pattern_tokens: Vec<String>,
+    // Stable integer token IDs, assigned in load order, for `encode`/`decode`,
+    // plus the reverse mapping so a token-ID stream can be turned back into
+    // text.
+    token_ids: HashMap<String, u32>,
+    id_tokens: Vec<String>,
 }
 
 impl Tokenizer {
     fn load(path: &str) -> io::Result<Self> {
         let file = File::open(path)?;
         let mut vocab = HashMap::new();
+        let mut token_ids = HashMap::new();
+        let mut id_tokens = Vec::new();
         let mut max_len = 0;
 
         for line in io::BufReader::new(file).lines() {
             let line = line?;
             if line.starts_with("Token\t") { continue; } // Header
-            
+
             if let Some((token, score_str)) = line.rsplit_once('\t') {
                 // Determine max len (in chars) for optimization
                 let char_count = token.chars().count();
                 if char_count > max_len {
                     max_len = char_count;
                 }
-                
+
                 // Parse Score
                 let score = score_str.trim().parse::<f64>().unwrap_or(0.0);
 
                 // We assume tokens.txt has escaped chars like \n, \r
                 // We need to unescape them to match correctly against real text
                 let unescaped = token.replace("\\n", "\n").replace("\\r", "\r");
-                vocab.insert(unescaped, score); 
+                token_ids.insert(unescaped.clone(), id_tokens.len() as u32);
+                id_tokens.push(unescaped.clone());
+                vocab.insert(unescaped, score);
             }
         }
-        
-        Ok(Self { vocab, max_token_len: max_len })
+
+        Ok(Self {
+            vocab,
+            max_token_len: max_len,
+            matcher: None,
+            pattern_tokens: Vec::new(),
+            token_ids,
+            id_tokens,
+        })
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokens = Vec::new();
-        let mut cursor = 0; // Index in the 'chars' vector, not byte offset
+    /// Load a vocab from the binary format written by `save_binary`: raw
+    /// token bytes, assigned IDs, and scores, with no text escaping needed.
+    fn load_binary(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let vocab_file: VocabFile = bincode::deserialize_from(io::BufReader::new(file))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut vocab = HashMap::new();
+        let mut token_ids = HashMap::new();
+        let mut id_tokens = vec![String::new(); vocab_file.entries.len()];
+
+        for entry in vocab_file.entries {
+            let token = String::from_utf8(entry.bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            token_ids.insert(token.clone(), entry.id);
+            id_tokens[entry.id as usize] = token.clone();
+            vocab.insert(token, entry.score);
+        }
+
+        Ok(Self {
+            vocab,
+            max_token_len: vocab_file.max_token_len,
+            matcher: None,
+            pattern_tokens: Vec::new(),
+            token_ids,
+            id_tokens,
+        })
+    }
+
+    /// Persist the vocab in the binary format: a header (`max_token_len`,
+    /// vocab size) followed by one `(id, bytes, score)` entry per token.
+    fn save_binary(&self, path: &str) -> io::Result<()> {
+        let entries = self.id_tokens.iter().enumerate()
+            .map(|(id, token)| VocabEntry {
+                id: id as u32,
+                bytes: token.as_bytes().to_vec(),
+                score: self.vocab[token],
+            })
+            .collect();
+
+        let vocab_file = VocabFile { max_token_len: self.max_token_len, entries };
+        let file = File::create(path)?;
+        bincode::serialize_into(BufWriter::new(file), &vocab_file)
+            .map_err(io::Error::other)
+    }
+
+    /// Export the vocab as the original tab-separated text format, for
+    /// humans to read or diff -- the binary format stays the source of
+    /// truth for round-tripping.
+    fn save_text(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "Token\tScore")?;
+        for token in &self.id_tokens {
+            let safe_token = token.replace('\r', "\\r").replace('\n', "\\n");
+            writeln!(file, "{}\t{:.4}", safe_token, self.vocab[token])?;
+        }
+        Ok(())
+    }
+
+    /// Encode `text` into the token-ID stream a downstream model expects,
+    /// instead of the `Vec<String>` `tokenize` returns.
+    ///
+    /// `tokenize`'s Viterbi fallback can emit a char that never made it into
+    /// the vocab (hence `token_ids`) -- that fallback always consumes
+    /// exactly one char (see `viterbi`'s `UNKNOWN_COST` path), so rather
+    /// than collapsing every one of those into a single shared `<unk>` ID
+    /// (which makes any text with several out-of-vocab chars impossible to
+    /// tell apart after a round trip), we encode the char's own codepoint
+    /// past the vocab's ID range. `decode` reverses the same mapping.
+    fn encode(&self, text: &str) -> Vec<u32> {
+        let vocab_size = self.id_tokens.len() as u32;
+        self.tokenize(text).iter()
+            .map(|t| match self.token_ids.get(t.as_str()) {
+                Some(&id) => id,
+                None => {
+                    let ch = t.chars().next().expect("fallback tokens are a single char");
+                    vocab_size + ch as u32
+                }
+            })
+            .collect()
+    }
+
+    /// Inverse of `encode`: reassemble text from a token-ID stream.
+    fn decode(&self, ids: &[u32]) -> String {
+        let vocab_size = self.id_tokens.len() as u32;
+        ids.iter()
+            .map(|&id| {
+                if id < vocab_size {
+                    self.id_tokens[id as usize].clone()
+                } else {
+                    char::from_u32(id - vocab_size).unwrap_or(char::REPLACEMENT_CHARACTER).to_string()
+                }
+            })
+            .collect()
+    }
+
+    /// Compile `vocab` into an Aho-Corasick automaton so `tokenize` and
+    /// `tokenize_top_k` can find every vocab token at every position in a
+    /// single linear pass over the text, rather than `max_token_len` hash
+    /// lookups per cursor. Opt-in: large vocabularies pay automaton build
+    /// time once, up front, to win back per-call lookup cost many times over.
+    fn with_aho_corasick(mut self) -> Self {
+        self.pattern_tokens = self.vocab.keys().cloned().collect();
+        self.matcher = AhoCorasick::new(&self.pattern_tokens).ok();
+        self
+    }
+
+    /// One linear pass over `text` collecting every vocab token match,
+    /// keyed by its starting byte offset, as `(end byte offset, score)`.
+    /// Returns an empty map when no automaton has been compiled, in which
+    /// case callers fall back to the per-length HashMap probe.
+    fn scan(&self, text: &str) -> HashMap<usize, Vec<(usize, f64)>> {
+        let mut by_start: HashMap<usize, Vec<(usize, f64)>> = HashMap::new();
+        if let Some(ac) = &self.matcher {
+            for mat in ac.find_overlapping_iter(text) {
+                let token = &self.pattern_tokens[mat.pattern().as_usize()];
+                if let Some(&score) = self.vocab.get(token) {
+                    by_start.entry(mat.start()).or_default().push((mat.end(), score));
+                }
+            }
+        }
+        by_start
+    }
+
+    // Unknown single characters must still advance the cursor, so they get a
+    // fixed fallback cost rather than being excluded from the search.
+    const UNKNOWN_COST: f64 = 20.0;
+
+    /// Forward pass of the Viterbi decoder: computes `back`, the backpointer
+    /// table used to recover the minimum-cost segmentation. Factored out of
+    /// `tokenize` so `count_tokens` can walk the same path without
+    /// materializing a `Vec<String>`.
+    ///
+    /// The old implementation picked the locally-best token at every cursor
+    /// position, which can force a worse split further along the string.
+    /// Instead we run a forward pass over character positions, treating each
+    /// vocab score as a log-probability contribution (`cost = -ln(score)`),
+    /// and take the globally cheapest path end-to-end.
+    fn viterbi<'t>(&self, text: &'t str) -> (Vec<(usize, char)>, Vec<Option<(usize, &'t str)>>) {
         let chars: Vec<(usize, char)> = text.char_indices().collect();
         let char_count = chars.len();
+        if char_count == 0 {
+            return (chars, Vec::new());
+        }
 
-        while cursor < char_count {
-            let mut best_match: Option<String> = None;
-            let mut best_len = 0;
-            let mut best_score = -1.0;
+        // best[i] = lowest total cost to reach character position i.
+        // back[i] = (prev position, token slice) that achieved best[i].
+        let mut best: Vec<f64> = vec![f64::INFINITY; char_count + 1];
+        let mut back: Vec<Option<(usize, &str)>> = vec![None; char_count + 1];
+        best[0] = 0.0;
 
-            // Greedy Search: Try ALL possible slices, pick highest Score
-            // Start from min(remaining_chars, max_token_len)
-            let remaining = char_count - cursor;
-            let search_depth = std::cmp::min(remaining, self.max_token_len);
-            
-            // Iterate length j from 1 to search_depth
-            for j in 1..=search_depth {
-                 let start_byte = chars[cursor].0;
-                 
-                 // Calculate end byte
-                 let end_index = cursor + j;
-                 let end_byte = if end_index < char_count { 
-                     chars[end_index].0 
-                 } else { 
-                     text.len() 
-                 };
-                 
-                 let slice = &text[start_byte..end_byte];
-                 
-                 if let Some(&score) = self.vocab.get(slice) {
-                     // Check if this token is "better" than what we found so far
-                     // Priority: Higher Score > Longer Length
-                     if score > best_score || (score == best_score && j > best_len) {
-                        best_match = Some(slice.to_string());
-                        best_len = j; 
-                        best_score = score;
-                     }
-                 }
+        // With an automaton compiled, find every vocab match in one pass
+        // instead of re-probing the HashMap for each candidate length below.
+        let matches_by_start = self.scan(text);
+        let mut byte_to_idx: HashMap<usize, usize> = chars.iter().enumerate().map(|(idx, &(b, _))| (b, idx)).collect();
+        byte_to_idx.insert(text.len(), char_count);
+
+        for i in 0..char_count {
+            if !best[i].is_finite() {
+                continue;
             }
 
-            if let Some(token) = best_match {
-                tokens.push(token);
-                cursor += best_len;
-            } else {
-                // If no match found, consume one character
-                let start_byte = chars[cursor].0;
-                let end_index = cursor + 1;
-                let end_byte = if end_index < char_count { chars[end_index].0 } else { text.len() };
-                
-                let char_slice = &text[start_byte..end_byte];
-                tokens.push(char_slice.to_string());
-                cursor += 1;
+            let start_byte = chars[i].0;
+
+            // An absent entry here means the automaton found no vocab token
+            // starting at this byte -- not that no automaton was compiled.
+            // Only fall back to the per-length HashMap probe in the latter
+            // case; otherwise most positions (real text rarely starts a
+            // vocab token at every char) would re-pay the O(max_token_len)
+            // probe the automaton exists to avoid.
+            if self.matcher.is_none() {
+                let search_depth = std::cmp::min(char_count - i, self.max_token_len);
+                for j in 1..=search_depth {
+                    let end_index = i + j;
+                    let end_byte = if end_index < char_count { chars[end_index].0 } else { text.len() };
+                    let slice = &text[start_byte..end_byte];
+
+                    if let Some(&score) = self.vocab.get(slice) {
+                        let cost = -score.max(f64::EPSILON).ln();
+                        let candidate = best[i] + cost;
+                        if candidate < best[end_index] {
+                            best[end_index] = candidate;
+                            back[end_index] = Some((i, slice));
+                        }
+                    }
+                }
+            } else if let Some(matches) = matches_by_start.get(&start_byte) {
+                for &(end_byte, score) in matches {
+                    let end_index = byte_to_idx[&end_byte];
+                    let slice = &text[start_byte..end_byte];
+                    let cost = -score.max(f64::EPSILON).ln();
+                    let candidate = best[i] + cost;
+                    if candidate < best[end_index] {
+                        best[end_index] = candidate;
+                        back[end_index] = Some((i, slice));
+                    }
+                }
             }
+
+            // Fallback: consume a single unknown char so every position stays reachable.
+            let end_index = i + 1;
+            let end_byte = if end_index < char_count { chars[end_index].0 } else { text.len() };
+            let char_slice = &text[start_byte..end_byte];
+            let candidate = best[i] + Self::UNKNOWN_COST;
+            if candidate < best[end_index] {
+                best[end_index] = candidate;
+                back[end_index] = Some((i, char_slice));
+            }
+        }
+
+        (chars, back)
+    }
+
+    /// Minimum-cost segmentation via dynamic programming (Viterbi-style).
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let (chars, back) = self.viterbi(text);
+        let char_count = chars.len();
+        if char_count == 0 {
+            return Vec::new();
         }
+
+        // Backtrack from n to recover the token list.
+        let mut tokens = Vec::with_capacity(char_count / 4);
+        let mut pos = char_count;
+        while pos > 0 {
+            let (prev, slice) = back[pos].expect("best[] is reachable at every position via the fallback path");
+            tokens.push(slice.to_string());
+            pos = prev;
+        }
+        tokens.reverse();
         tokens
     }
+
+    /// Counts the tokens the Viterbi decoder would emit for `text`, without
+    /// materializing the `Vec<String>` -- cheap enough to call on every
+    /// keystroke for a live "remaining tokens" indicator.
+    fn count_tokens(&self, text: &str) -> usize {
+        let (chars, back) = self.viterbi(text);
+        let char_count = chars.len();
+
+        let mut count = 0;
+        let mut pos = char_count;
+        while pos > 0 {
+            let (prev, _) = back[pos].expect("best[] is reachable at every position via the fallback path");
+            count += 1;
+            pos = prev;
+        }
+        count
+    }
+
+    /// Tokenizes `text` but stops once `max_tokens` tokens have been
+    /// emitted, so callers can enforce a hard context-window budget without
+    /// paying for a full Viterbi pass over text they'll never use. Returns
+    /// the (possibly truncated) tokens, how many budget slots are left, and
+    /// whether truncation occurred.
+    ///
+    /// The old implementation ran `tokenize` over the whole text and
+    /// truncated the result afterward -- correct output, but O(text length)
+    /// work no matter how small `max_tokens` was. Since no vocab token is
+    /// longer than `max_token_len` chars, `max_tokens` of them can never
+    /// span more than `max_tokens * max_token_len` chars, so we bound the
+    /// Viterbi pass itself to that prefix instead of the whole input.
+    fn tokenize_within(&self, text: &str, max_tokens: usize) -> (Vec<String>, usize, bool) {
+        if max_tokens == 0 {
+            return (Vec::new(), 0, !text.is_empty());
+        }
+
+        let char_limit = max_tokens.saturating_mul(self.max_token_len.max(1));
+        let prefix_end = text.char_indices().nth(char_limit).map(|(b, _)| b).unwrap_or(text.len());
+        let bounded_by_text = prefix_end < text.len();
+
+        let mut tokens = self.tokenize(&text[..prefix_end]);
+        let bounded_by_budget = tokens.len() > max_tokens;
+        if bounded_by_budget {
+            tokens.truncate(max_tokens);
+        }
+
+        let remaining = max_tokens - tokens.len();
+        (tokens, remaining, bounded_by_budget || bounded_by_text)
+    }
+
+    /// Beam search: returns up to `k` segmentations, ranked by descending
+    /// cumulative log-probability, instead of the single best path.
+    ///
+    /// At every character position each live hypothesis is expanded by every
+    /// matching vocab token (plus a single-char fallback). The competing
+    /// extension scores at that position are passed through a softmax so
+    /// they form a proper probability distribution -- otherwise longer and
+    /// shorter token choices aren't comparable -- and the resulting
+    /// log-probabilities accumulate into each hypothesis's total. The
+    /// frontier is kept to at most `beam_width` hypotheses at a time via a
+    /// `BinaryHeap` ordered so the worst hypothesis is cheapest to drop.
+    fn tokenize_top_k(&self, text: &str, beam_width: usize, k: usize) -> Vec<(Vec<String>, f64)> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let char_count = chars.len();
+        if char_count == 0 {
+            return Vec::new();
+        }
+
+        // One linear pass over the text when an automaton is compiled,
+        // instead of re-probing the HashMap per candidate length below.
+        let matches_by_start = self.scan(text);
+        let mut byte_to_idx: HashMap<usize, usize> = chars.iter().enumerate().map(|(idx, &(b, _))| (b, idx)).collect();
+        byte_to_idx.insert(text.len(), char_count);
+
+        let mut frontier = vec![Hypothesis { tokens: Vec::new(), cursor: 0, log_prob: 0.0 }];
+        let mut results: Vec<Hypothesis> = Vec::new();
+
+        while !frontier.is_empty() {
+            let mut next: BinaryHeap<Reverse<Hypothesis>> = BinaryHeap::new();
+
+            for hyp in frontier {
+                let start_byte = chars[hyp.cursor].0;
+
+                // Collect every matching vocab token plus the unknown-char fallback.
+                // `viterbi` always adds the fallback alongside real matches at every
+                // position -- do the same here, so the beam considers the same set
+                // of segmentations the DP decoder would.
+                let mut candidates: Vec<(usize, &str, f64)> = Vec::new();
+                // Same reasoning as `viterbi`: a missing `matches_by_start`
+                // entry means no vocab token starts here, not that no
+                // automaton was compiled -- only probe the HashMap when
+                // there's genuinely no automaton to rely on.
+                if self.matcher.is_none() {
+                    let search_depth = std::cmp::min(char_count - hyp.cursor, self.max_token_len);
+                    for j in 1..=search_depth {
+                        let end_index = hyp.cursor + j;
+                        let end_byte = if end_index < char_count { chars[end_index].0 } else { text.len() };
+                        let slice = &text[start_byte..end_byte];
+                        if let Some(&score) = self.vocab.get(slice) {
+                            candidates.push((end_index, slice, score));
+                        }
+                    }
+                } else if let Some(matches) = matches_by_start.get(&start_byte) {
+                    for &(end_byte, score) in matches {
+                        let end_index = byte_to_idx[&end_byte];
+                        candidates.push((end_index, &text[start_byte..end_byte], score));
+                    }
+                }
+                let end_index = hyp.cursor + 1;
+                let end_byte = if end_index < char_count { chars[end_index].0 } else { text.len() };
+                let slice = &text[start_byte..end_byte];
+                candidates.push((end_index, slice, f64::EPSILON));
+
+                // Softmax over the competing raw scores at this position.
+                let max_score = candidates.iter().map(|&(_, _, s)| s).fold(f64::MIN, f64::max);
+                let exp_sum: f64 = candidates.iter().map(|&(_, _, s)| (s - max_score).exp()).sum();
+
+                for (end_index, slice, score) in candidates {
+                    let prob = (score - max_score).exp() / exp_sum;
+                    let mut tokens = hyp.tokens.clone();
+                    tokens.push(slice.to_string());
+                    let extended = Hypothesis {
+                        tokens,
+                        cursor: end_index,
+                        log_prob: hyp.log_prob + prob.ln(),
+                    };
+
+                    next.push(Reverse(extended));
+                    if next.len() > beam_width {
+                        next.pop(); // Evict the worst hypothesis.
+                    }
+                }
+            }
+
+            let mut live = Vec::with_capacity(next.len());
+            for Reverse(hyp) in next {
+                if hyp.cursor >= char_count {
+                    results.push(hyp);
+                } else {
+                    live.push(hyp);
+                }
+            }
+            frontier = live;
+        }
+
+        results.sort_by(|a, b| b.log_prob.partial_cmp(&a.log_prob).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(k);
+        results.into_iter().map(|h| (h.tokens, h.log_prob)).collect()
+    }
+}
+
+/// Load the vocab the binary format is meant to be loaded from
+/// (`tokens.bin`); the tab-separated `tokens.txt` the trainer writes is only
+/// a fallback for the first run, and gets mirrored into `tokens.bin` (plus a
+/// human-readable `tokens_export.txt`, demonstrating the optional text
+/// export) so every subsequent run takes the binary path.
+fn load_vocab() -> io::Result<Tokenizer> {
+    match Tokenizer::load_binary("tokens.bin") {
+        Ok(tokenizer) => Ok(tokenizer),
+        Err(_) => {
+            let tokenizer = Tokenizer::load("tokens.txt")?;
+            if let Err(e) = tokenizer.save_binary("tokens.bin") {
+                eprintln!("Warning: failed to write 'tokens.bin': {}", e);
+            }
+            if let Err(e) = tokenizer.save_text("tokens_export.txt") {
+                eprintln!("Warning: failed to write 'tokens_export.txt': {}", e);
+            }
+            Ok(tokenizer)
+        }
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    
+
     // Default demo text if no args provided
     let default_text = "the quick brown fox jumps over the lazy dog two zero zero nine";
     let input_text = if args.len() > 1 { &args[1] } else { default_text };
 
-    match Tokenizer::load("tokens.txt") {
+    match load_vocab().map(Tokenizer::with_aho_corasick) {
         Ok(tokenizer) => {
             println!("Loaded vocab size: {} tokens", tokenizer.vocab.len());
             println!("Max token length in vocab: {} chars", tokenizer.max_token_len);
@@ -119,7 +533,91 @@ fn main() {
             
             // Pretty print reconstruction check
             println!("\nReconstructed: '{}'", result.join(""));
+
+            // n-best via beam search, for downstream reranking
+            let beam_width = 8;
+            let top_k = 3;
+            let candidates = tokenizer.tokenize_top_k(input_text, beam_width, top_k);
+            println!("\nTop {} segmentations (beam width {}):", top_k, beam_width);
+            for (i, (tokens, log_prob)) in candidates.iter().enumerate() {
+                println!("  #{}: log_prob={:.4} {:?}", i + 1, log_prob, tokens);
+            }
+
+            // Token-ID stream for feeding a downstream model
+            let ids = tokenizer.encode(input_text);
+            println!("\nEncoded ({} ids): {:?}", ids.len(), ids);
+            println!("Decoded: '{}'", tokenizer.decode(&ids));
+
+            // Budget-aware tokenization, e.g. for a fixed context window
+            let max_tokens = 5;
+            let (budgeted, remaining, truncated) = tokenizer.tokenize_within(input_text, max_tokens);
+            println!("\nBudgeted to {} tokens (truncated={}, remaining={}): {:?}",
+                max_tokens, truncated, remaining, budgeted);
+            println!("count_tokens: {}", tokenizer.count_tokens(input_text));
         }
-        Err(e) => eprintln!("Failed to load 'tokens.txt'. Make sure you ran the training first.\nError: {}", e),
+        Err(e) => eprintln!("Failed to load vocab from 'tokens.bin' or 'tokens.txt'. Make sure you ran the training first.\nError: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demo_tokenizer() -> Tokenizer {
+        let pairs = [("the", 0.9), ("quick", 0.8), ("fox", 0.7), ("lazy", 0.6), ("dog", 0.5)];
+        let vocab: HashMap<String, f64> = pairs.iter().map(|&(t, s)| (t.to_string(), s)).collect();
+        let id_tokens: Vec<String> = pairs.iter().map(|&(t, _)| t.to_string()).collect();
+        let token_ids: HashMap<String, u32> = id_tokens.iter().enumerate()
+            .map(|(id, t)| (t.clone(), id as u32))
+            .collect();
+        let max_token_len = id_tokens.iter().map(|t| t.chars().count()).max().unwrap_or(1);
+
+        Tokenizer { vocab, max_token_len, matcher: None, pattern_tokens: Vec::new(), token_ids, id_tokens }
+    }
+
+    #[test]
+    fn tokenize_reconstructs_the_input() {
+        let t = demo_tokenizer();
+        let tokens = t.tokenize("the quick z fox");
+        assert_eq!(tokens.join(""), "the quick z fox");
+    }
+
+    #[test]
+    fn tokenize_matches_with_and_without_aho_corasick() {
+        let plain = demo_tokenizer();
+        let compiled = demo_tokenizer().with_aho_corasick();
+        let text = "the lazy fox and the quick dog";
+        assert_eq!(plain.tokenize(text), compiled.tokenize(text));
+    }
+
+    #[test]
+    fn count_tokens_matches_tokenize_len() {
+        let t = demo_tokenizer();
+        assert_eq!(t.count_tokens("the quick z fox"), t.tokenize("the quick z fox").len());
+    }
+
+    #[test]
+    fn tokenize_top_k_includes_the_viterbi_best_path() {
+        let t = demo_tokenizer();
+        let best = t.tokenize("the quick fox");
+        let beam = t.tokenize_top_k("the quick fox", 8, 5);
+        assert!(beam.iter().any(|(tokens, _)| tokens == &best));
+    }
+
+    #[test]
+    fn encode_decode_round_trips_text_with_out_of_vocab_chars() {
+        let t = demo_tokenizer();
+        let text = "the quick z fox!";
+        let ids = t.encode(text);
+        assert_eq!(t.decode(&ids), text);
+    }
+
+    #[test]
+    fn tokenize_within_never_exceeds_the_budget() {
+        let t = demo_tokenizer();
+        let (tokens, remaining, truncated) = t.tokenize_within("the quick brown fox jumps", 3);
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(remaining, 0);
+        assert!(truncated);
     }
 }
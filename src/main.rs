@@ -1,181 +1,80 @@
-use dashmap::DashMap;
-use rand::prelude::*;
+mod algorithms;
+
+use algorithms::ant_colony::AntColony;
+use memmap2::Mmap;
 use rayon::prelude::*;
-use std::fs;
+use std::fs::{self, File};
 use std::time::Instant;
 
-const Q: f64 = 1.0; // Reduced to flatten hierarchy (less exponential reward for length)
-const INITIAL_PHEROMONE: f64 = 1.0; 
-const ALPHA: f64 = 1.0; 
-const BETA: f64 = 2.0;  
-const MAX_TOKEN_LEN: usize = 10; 
-
-struct AntColony<'a> {
-    // Shared pheromone map: Token -> Strength
-    // DashMap for thread-safe concurrent access.
-    pheromones: DashMap<&'a str, f64>, 
+/// Memory-map `path` and hand back the whole file as a `&'static str`.
+///
+/// The mapping is leaked for the program's lifetime (`Box::leak`) so every
+/// `&str` token sliced out of it -- all the way down into `AntColony`'s
+/// `pheromones` map -- can borrow the corpus with zero copies. The OS pages
+/// data in on demand instead of us reading the whole file into process
+/// memory up front, so corpora far larger than RAM still work.
+fn mmap_corpus(path: &str) -> std::io::Result<&'static str> {
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file)? };
+    let bytes: &'static [u8] = Box::leak(Box::new(mmap));
+    std::str::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
 }
 
-impl<'a> AntColony<'a> {
-    fn new() -> Self {
-        Self {
-            pheromones: DashMap::new(),
-        }
-    }
-
-    fn get_pheromone(&self, token: &str) -> f64 {
-        // If not present, default to INITIAL_PHEROMONE
-        match self.pheromones.get(token) {
-            Some(val) => *val,
-            None => INITIAL_PHEROMONE,
+/// Split `text` into chunks of roughly `chunk_size` bytes, backing each
+/// boundary off to the previous char boundary so every chunk stays valid
+/// UTF-8 (no `from_utf8` fixups or lossy replacement needed downstream).
+fn split_utf8_chunks(text: &str, chunk_size: usize) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut chunks = Vec::with_capacity(bytes.len() / chunk_size + 1);
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let mut end = std::cmp::min(start + chunk_size, bytes.len());
+        while end < bytes.len() && !text.is_char_boundary(end) {
+            end -= 1;
         }
-    }
-
-    // Heuristic: Prefer longer tokens. 
-    // This drives the ants to discover chunks rather than single chars.
-    fn get_heuristic(&self, token: &str) -> f64 {
-        (token.len() as f64).powf(BETA)
-    }
-
-    /// Run a single ant on a slice of text (e.g., a line).
-    /// Returns the list of tokens chosen and the number of steps.
-    fn traverse(&self, text: &'a str, rng: &mut ThreadRng) -> (Vec<&'a str>, usize) {
-        let mut tokens = Vec::with_capacity(text.len() / 4);
-        let mut cursor = 0;
-        let len = text.len();
-
-        while cursor < len {
-            // Identify candidates
-            // We must use char_indices to ensure we slice at valid UTF-8 boundaries.
-            let remaining = &text[cursor..];
-            
-            // Collect valid slices up to MAX_TOKEN_LEN characters long
-            // Note: max_len logic is now based on chars, not bytes, which matches user intuition better.
-            let mut candidates = Vec::with_capacity(MAX_TOKEN_LEN);
-            let mut total_prob = 0.0;
-
-            for (byte_offset, ch) in remaining.char_indices().take(MAX_TOKEN_LEN) {
-                let end = byte_offset + ch.len_utf8();
-                let token_slice = &remaining[..end]; // Valid UTF-8 slice
-                
-                let tau = self.get_pheromone(token_slice);
-                let eta = self.get_heuristic(token_slice);
-                
-                let prob = tau.ln().max(0.0001) * eta;
-                total_prob += prob;
-                candidates.push((token_slice, prob));
-            }
-
-            // Selection
-            // Default check if something went wrong (shouldn't if text is valid)
-            if candidates.is_empty() {
-                break;
-            }
-
-            let mut selected_token = candidates[0].0; 
-            
-            if total_prob > 0.0 {
-                let threshold = rng.gen::<f64>() * total_prob;
-                let mut current_sum = 0.0;
-                
-                for (tok, p) in candidates {
-                    current_sum += p;
-                    if current_sum >= threshold {
-                        selected_token = tok;
-                        break;
-                    }
-                }
+        if end <= start {
+            // A single char longer than chunk_size; take it whole.
+            end = start + 1;
+            while end < bytes.len() && !text.is_char_boundary(end) {
+                end += 1;
             }
-
-            tokens.push(selected_token);
-            cursor += selected_token.len();
         }
-
-        let steps = tokens.len();
-        (tokens, steps)
+        chunks.push(&text[start..end]);
+        start = end;
     }
 
-    /// Genetic Algorithm Selection:
-    /// Keep Top 20% by pruning the bottom 80%.
-    fn natural_selection(&self) {
-        if self.pheromones.is_empty() { return; }
-
-        let active_tokens = self.pheromones.len();
-        
-        // 1. Estimate Pruning Threshold via Sampling
-        // Sorting 6 million items is slow. We can just purge anything below a moving average
-        // or just strict top-k. Let's stick to the user's sort for now but optimize logic.
-        
-        let mut scores: Vec<f64> = self.pheromones.iter().map(|r| *r.value()).collect();
-        // Sort descending
-        scores.sort_unstable_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
-        
-        let keep_ratio = 0.20;
-        let cut_index = ((active_tokens as f64 * keep_ratio) as usize).max(1);
-        let threshold = scores[cut_index.min(active_tokens - 1)];
-
-        println!("  Natural Selection: Keeping top {:.0}% (Threshold: {:.4}). Active Tokens: {}", 
-            keep_ratio * 100.0, threshold, active_tokens);
-
-        // 2. Prune Weak Links
-        self.pheromones.retain(|_, v| *v >= threshold);
-    }
-
-    /// Deposit pheromones using Logistic Growth (Soft Cap)
-    /// This allows new tokens to grow fast, but prevents established ones from becoming infinite.
-    fn deposit(&self, path: &[&'a str], _steps: usize) {
-        const MAX_SCORE: f64 = 100000000.0; // The Carrying Capacity (K)
-
-        for token in path {
-            let len = token.len();
-            if len > 1 {
-                let reward = ((len - 1) as f64).powf(Q);
-                
-                // DashMap allows atomic updates via entry API
-                let mut entry = self.pheromones.entry(token).or_insert(INITIAL_PHEROMONE);
-                let current_val = *entry;
-                
-                // Logistic Update: dP = Reward * (1 - P/K)
-                // If P is small, dP â‰ˆ Reward (Fast Growth)
-                // If P -> K, dP -> 0 (Saturation)
-                let delta = reward * (1.0 - current_val / MAX_SCORE);
-                
-                if delta > 0.0 {
-                    *entry += delta;
-                }
-            }
-        }
-    }
+    chunks
 }
 
 fn main() {
     // 1. Load Data
     let file_path = "text8"; // The 100MB corpus
-    println!("Loading {}...", file_path);
-    
-    // Read file. Unwraps are for simplicity in this example.
-    let raw_text = match fs::read_to_string(file_path) {
+    println!("Loading {} (memory-mapped)...", file_path);
+
+    // Memory-map the corpus instead of `fs::read_to_string` so the OS pages
+    // data in on demand rather than us copying the whole file into the heap.
+    let raw_text: &'static str = match mmap_corpus(file_path) {
         Ok(t) => t,
         Err(_) => {
             println!("'text8' not found, falling back to 'sherlock.txt'");
-            fs::read_to_string("sherlock.txt").unwrap_or_else(|_| "sample text".repeat(100))
+            match mmap_corpus("sherlock.txt") {
+                Ok(t) => t,
+                Err(_) => Box::leak(fs::read_to_string("sherlock.txt").unwrap_or_else(|_| "sample text".repeat(100)).into_boxed_str()),
+            }
         }
     };
-    
+
     // Chunking Logic for Parallelism
     // If the file is 1 huge line (like text8), lines() gives 1 item => No parallelism.
     // We break it into fixed-size chunks (e.g., 500 chars) ensuring valid UTF-8.
     let chunk_size = 500;
-    
-    // Collecting char indices is memory intensive for 100MB (vector of tuples).
-    // Let's use a smarter iterator approach or just byte slicing if ASCII (text8 is ASCII).
-    // Text8 is pure ASCII lower case a-z and space. Byte slicing is safe.
+
+    // Chunk the mmap'd buffer directly into byte ranges for rayon, without
+    // copying -- char-boundary backoff keeps every chunk valid UTF-8.
     let lines: Vec<&str> = if raw_text.lines().count() < 1000 {
         println!("Detected monolithic text. Chunking into {}-byte segments...", chunk_size);
-        raw_text.as_bytes()
-            .chunks(chunk_size)
-            .map(|c| std::str::from_utf8(c).unwrap_or(""))
-            .collect()
+        split_utf8_chunks(raw_text, chunk_size)
     } else {
         println!("Detected structured text (lines). Using native newlines.");
         raw_text.lines()
@@ -201,12 +100,14 @@ fn main() {
             .collect();
 
         // Deposit Pheromones
-        results.into_par_iter().for_each(|(path, steps)| {
-            colony.deposit(&path, steps);
+        results.par_iter().for_each(|(path, steps)| {
+            colony.deposit(path, *steps);
         });
 
-        // Global Selection (Survival of the Fittest)
-        colony.natural_selection();
+        // Global Selection (Survival of the Fittest), scored against this
+        // generation's segmentation paths.
+        let paths: Vec<&[&str]> = results.iter().map(|(path, _)| path.as_slice()).collect();
+        colony.natural_selection(&paths);
 
         let elapsed = gen_start.elapsed();
         let vocab_size = colony.pheromones.len();